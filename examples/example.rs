@@ -1,6 +1,6 @@
 extern crate partition_identity;
 
-use partition_identity::{PartitionID, PartitionIDVariant};
+use partition_identity::{PartitionID, PartitionIDVariant, PartitionSource};
 use std::env;
 use std::process::exit;
 
@@ -20,6 +20,7 @@ fn main() {
                     println!("PartUUID: {:?}", PartitionID::by_id(PartitionIDVariant::PartUUID, &device));
                     println!("Path: {:?}", PartitionID::by_id(PartitionIDVariant::Path, &device));
                     println!("UUID: {:?}", PartitionID::by_id(PartitionIDVariant::UUID, &device));
+                    println!("DiskSeq: {:?}", PartitionID::get_source(PartitionSource::DiskSeq, &device));
                 }
             }
             "by-uuid" => {
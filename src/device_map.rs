@@ -0,0 +1,245 @@
+//! A single-pass scan of `/dev/disk/by-*` that builds a reverse map from
+//! canonical device nodes to every identity known for them.
+//!
+//! Resolving all identities of a handful of devices by calling
+//! [`PartitionID::get_source`](crate::PartitionID::get_source) repeatedly
+//! means one directory scan per identity, per device. `DeviceMap::scan`
+//! instead walks each `by-*` directory exactly once and canonicalizes every
+//! symlink it finds, so bulk callers pay for one scan of each directory no
+//! matter how many devices they care about.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Everything known about a single device node after a [`DeviceMap::scan`].
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Record {
+    /// The canonical `/dev/...` node this record describes.
+    pub name: Option<PathBuf>,
+    /// All `/dev/disk/by-id` aliases pointing at this device; it is common
+    /// for a single device to have more than one.
+    pub ids: Vec<String>,
+    pub label: Option<String>,
+    pub partlabel: Option<String>,
+    pub partuuid: Option<String>,
+    pub path: Option<String>,
+    pub uuid: Option<String>,
+    pub diskseq: Option<String>
+}
+
+/// A reverse map of every canonical device node found under
+/// `/dev/disk/by-*` to the identities that resolve to it.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceMap {
+    devices: BTreeMap<PathBuf, Record>
+}
+
+impl DeviceMap {
+    /// Scan `/dev/disk/by-id`, `by-label`, `by-partlabel`, `by-partuuid`,
+    /// `by-path`, `by-uuid`, and `by-diskseq` exactly once, canonicalizing
+    /// every symlink found, and build a snapshot of the whole topology.
+    ///
+    /// Directories that do not exist on this system (e.g. `by-diskseq` on
+    /// older kernels) are silently skipped rather than treated as an error.
+    pub fn scan() -> io::Result<Self> {
+        let mut devices: BTreeMap<PathBuf, Record> = BTreeMap::new();
+
+        scan_dir("/dev/disk/by-id", |name, target| {
+            entry(&mut devices, target).ids.push(name);
+        })?;
+
+        scan_dir("/dev/disk/by-label", |name, target| {
+            entry(&mut devices, target).label = Some(name);
+        })?;
+
+        scan_dir("/dev/disk/by-partlabel", |name, target| {
+            entry(&mut devices, target).partlabel = Some(name);
+        })?;
+
+        scan_dir("/dev/disk/by-partuuid", |name, target| {
+            entry(&mut devices, target).partuuid = Some(name);
+        })?;
+
+        scan_dir("/dev/disk/by-path", |name, target| {
+            entry(&mut devices, target).path = Some(name);
+        })?;
+
+        scan_dir("/dev/disk/by-uuid", |name, target| {
+            entry(&mut devices, target).uuid = Some(name);
+        })?;
+
+        scan_dir("/dev/disk/by-diskseq", |name, target| {
+            entry(&mut devices, target).diskseq = Some(name);
+        })?;
+
+        for (path, record) in devices.iter_mut() {
+            record.name = Some(path.clone());
+        }
+
+        Ok(Self { devices })
+    }
+
+    /// Look up the record for the device at `path`, canonicalizing it first.
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&Record> {
+        let canonical = path.as_ref().canonicalize().ok()?;
+        self.devices.get(&canonical)
+    }
+
+    /// Iterate over every device node and its record.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &Record)> {
+        self.devices.iter().map(|(path, record)| (path.as_path(), record))
+    }
+
+    /// Find the record whose `by-id` aliases include `id`.
+    pub fn by_id(&self, id: &str) -> Option<&Record> {
+        self.devices.values().find(|record| record.ids.iter().any(|i| i == id))
+    }
+
+    /// Find the record whose label is `label`.
+    pub fn by_label(&self, label: &str) -> Option<&Record> {
+        self.find_by(label, |record| record.label.as_deref())
+    }
+
+    /// Find the record whose partition label is `partlabel`.
+    pub fn by_partlabel(&self, partlabel: &str) -> Option<&Record> {
+        self.find_by(partlabel, |record| record.partlabel.as_deref())
+    }
+
+    /// Find the record whose partition UUID is `partuuid`.
+    pub fn by_partuuid(&self, partuuid: &str) -> Option<&Record> {
+        self.find_by(partuuid, |record| record.partuuid.as_deref())
+    }
+
+    /// Find the record whose `by-path` alias is `path`.
+    pub fn by_path(&self, path: &str) -> Option<&Record> {
+        self.find_by(path, |record| record.path.as_deref())
+    }
+
+    /// Find the record whose UUID is `uuid`.
+    pub fn by_uuid(&self, uuid: &str) -> Option<&Record> {
+        self.find_by(uuid, |record| record.uuid.as_deref())
+    }
+
+    /// Find the record whose disk sequence number is `diskseq`.
+    pub fn by_diskseq(&self, diskseq: &str) -> Option<&Record> {
+        self.find_by(diskseq, |record| record.diskseq.as_deref())
+    }
+
+    fn find_by<'a>(
+        &'a self,
+        needle: &str,
+        field: impl Fn(&'a Record) -> Option<&'a str>
+    ) -> Option<&'a Record> {
+        self.devices.values().find(|record| field(record) == Some(needle))
+    }
+}
+
+fn entry(devices: &mut BTreeMap<PathBuf, Record>, canonical: PathBuf) -> &mut Record {
+    devices.entry(canonical).or_default()
+}
+
+/// Read every entry of `dir`, canonicalize its symlink target, and invoke
+/// `func` with the entry's file name and the canonical `/dev/...` node it
+/// points at. Missing directories are treated as empty rather than erroring.
+fn scan_dir(dir: &str, mut func: impl FnMut(String, PathBuf)) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref why) if why.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(why) => return Err(why)
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue
+        };
+
+        if let Ok(canonical) = entry.path().canonicalize() {
+            func(name, canonical);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn scan_dir_canonicalizes_symlinks_and_skips_missing_dirs() {
+        let dir = temp_dir("partition-identity-test-device-map-scan-dir");
+        let target = std::env::temp_dir().join("partition-identity-test-device-map-scan-dir-target");
+        fs::write(&target, b"").unwrap();
+        symlink(&target, dir.join("by-uuid-link")).unwrap();
+
+        let canonical_target = target.canonicalize().unwrap();
+        let mut found = Vec::new();
+        scan_dir(dir.to_str().unwrap(), |name, target| found.push((name, target))).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        fs::remove_file(&target).unwrap();
+        assert_eq!(found, vec![("by-uuid-link".to_owned(), canonical_target)]);
+
+        // A directory that doesn't exist at all is treated as empty, not an error.
+        scan_dir("/nonexistent/partition-identity-by-uuid", |_, _| panic!("should not be called")).unwrap();
+    }
+
+    #[test]
+    fn entry_reuses_the_record_for_the_same_canonical_path() {
+        let mut devices = BTreeMap::new();
+        let path = PathBuf::from("/dev/sda1");
+
+        entry(&mut devices, path.clone()).label = Some("root".into());
+        entry(&mut devices, path.clone()).uuid = Some("abcd".into());
+
+        assert_eq!(devices.len(), 1);
+        let record = &devices[&path];
+        assert_eq!(record.label.as_deref(), Some("root"));
+        assert_eq!(record.uuid.as_deref(), Some("abcd"));
+    }
+
+    #[test]
+    fn lookups_find_the_record_by_each_identity() {
+        let dir = temp_dir("partition-identity-test-device-map-lookups");
+        let device = dir.join("sda1");
+        fs::write(&device, b"").unwrap();
+
+        let mut devices = BTreeMap::new();
+        let canonical = device.canonicalize().unwrap();
+        let record = entry(&mut devices, canonical.clone());
+        record.ids.push("disk-id".into());
+        record.label = Some("root".into());
+        record.partlabel = Some("rootfs".into());
+        record.partuuid = Some("partuuid-1".into());
+        record.path = Some("pci-0000".into());
+        record.uuid = Some("uuid-1".into());
+        record.diskseq = Some("1".into());
+        record.name = Some(canonical);
+
+        let map = DeviceMap { devices };
+
+        assert!(map.get(&device).is_some());
+        assert_eq!(map.by_id("disk-id").unwrap().label.as_deref(), Some("root"));
+        assert_eq!(map.by_label("root").unwrap().uuid.as_deref(), Some("uuid-1"));
+        assert_eq!(map.by_partlabel("rootfs").unwrap().partuuid.as_deref(), Some("partuuid-1"));
+        assert_eq!(map.by_partuuid("partuuid-1").unwrap().partlabel.as_deref(), Some("rootfs"));
+        assert_eq!(map.by_path("pci-0000").unwrap().diskseq.as_deref(), Some("1"));
+        assert_eq!(map.by_uuid("uuid-1").unwrap().path.as_deref(), Some("pci-0000"));
+        assert_eq!(map.by_diskseq("1").unwrap().label.as_deref(), Some("root"));
+        assert!(map.by_id("no-such-id").is_none());
+        assert_eq!(map.iter().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+//! Enumerating the partitions of a whole disk, and resolving every known
+//! identity of each one in a single pass.
+
+use crate::{gpt, DeviceMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Every identity known for a single partition, gathered from a
+/// [`DeviceMap`] scan and, where those are missing, a direct GPT read.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct PartitionIdentities {
+    /// The canonical `/dev/...` node of this partition.
+    pub path: PathBuf,
+    pub ids: Vec<String>,
+    pub label: Option<String>,
+    pub partlabel: Option<String>,
+    pub partuuid: Option<String>,
+    pub by_path: Option<String>,
+    pub uuid: Option<String>,
+    pub diskseq: Option<String>
+}
+
+/// Walk `/sys/block/<disk>/` for child partitions of the whole-disk device
+/// `disk` (e.g. `/dev/nvme0n1`), and resolve every known identity of each.
+pub fn partitions_of(disk: &Path) -> io::Result<Vec<PartitionIdentities>> {
+    let disk_name = disk
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "disk path has no file name"))?;
+
+    let sysfs_disk = PathBuf::from("/sys/block").join(disk_name);
+    let map = DeviceMap::scan()?;
+    let mut partitions = Vec::new();
+
+    for entry in fs::read_dir(&sysfs_disk)?.filter_map(|entry| entry.ok()) {
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue
+        };
+
+        // Partition subdirectories of a disk's sysfs entry contain a
+        // `partition` attribute file; the disk's other sysfs children
+        // (`queue`, `holders`, ...) do not.
+        if !entry.path().join("partition").is_file() {
+            continue;
+        }
+
+        let partition_path = PathBuf::from("/dev").join(&name);
+        if let Ok(canonical) = partition_path.canonicalize() {
+            partitions.push(resolve(&map, &canonical));
+        }
+    }
+
+    Ok(partitions)
+}
+
+fn resolve(map: &DeviceMap, partition_path: &Path) -> PartitionIdentities {
+    let record = map.get(partition_path);
+
+    let mut partuuid = record.and_then(|record| record.partuuid.clone());
+    let mut partlabel = record.and_then(|record| record.partlabel.clone());
+
+    if partuuid.is_none() || partlabel.is_none() {
+        if let Ok(Some((disk, number))) = gpt::disk_and_partition_number(partition_path) {
+            if let Ok(Some(entry)) = gpt::read_entry(&disk, number) {
+                partuuid.get_or_insert(entry.partuuid);
+                partlabel.get_or_insert(entry.partlabel);
+            }
+        }
+    }
+
+    PartitionIdentities {
+        path: partition_path.to_owned(),
+        ids: record.map(|record| record.ids.clone()).unwrap_or_default(),
+        label: record.and_then(|record| record.label.clone()),
+        partlabel,
+        partuuid,
+        by_path: record.and_then(|record| record.path.clone()),
+        uuid: record.and_then(|record| record.uuid.clone()),
+        diskseq: record.and_then(|record| record.diskseq.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeviceMap;
+
+    #[test]
+    fn resolve_returns_path_only_when_nothing_is_known() {
+        let map = DeviceMap::default();
+        let path = Path::new("/nonexistent/partition-identity-test-disk-resolve");
+
+        let identities = resolve(&map, path);
+
+        assert_eq!(identities.path, path);
+        assert!(identities.ids.is_empty());
+        assert!(identities.label.is_none());
+        assert!(identities.partlabel.is_none());
+        assert!(identities.partuuid.is_none());
+        assert!(identities.by_path.is_none());
+        assert!(identities.uuid.is_none());
+        assert!(identities.diskseq.is_none());
+    }
+
+    #[test]
+    fn partitions_of_rejects_a_disk_path_with_no_file_name() {
+        let err = partitions_of(Path::new("/")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn partitions_of_errors_when_the_disk_is_not_in_sysfs() {
+        let disk = Path::new("/dev/partition-identity-test-nonexistent-disk");
+        let err = partitions_of(disk).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,43 @@
+//! Errors returned when resolving a [`PartitionID`](crate::PartitionID)
+//! against the filesystem.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Why a `PartitionID` could not be resolved.
+#[derive(Debug)]
+pub enum PartitionError {
+    /// The `/dev/disk/by-<x>` directory for this source could not be read,
+    /// most often because it does not exist, which happens in containers,
+    /// non-udev systems, and WSL.
+    DirUnavailable { path: PathBuf, source: io::Error },
+    /// The directory was read successfully, but no entry matched.
+    NotFound,
+    /// A path could not be canonicalized while searching for a match.
+    Canonicalize { path: PathBuf, source: io::Error }
+}
+
+impl fmt::Display for PartitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartitionError::DirUnavailable { path, source } => {
+                write!(f, "unable to find {:?}: {}", path, source)
+            }
+            PartitionError::NotFound => write!(f, "no matching partition identity was found"),
+            PartitionError::Canonicalize { path, source } => {
+                write!(f, "failed to canonicalize {:?}: {}", path, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PartitionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PartitionError::DirUnavailable { source, .. } => Some(source),
+            PartitionError::NotFound => None,
+            PartitionError::Canonicalize { source, .. } => Some(source)
+        }
+    }
+}
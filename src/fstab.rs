@@ -0,0 +1,180 @@
+//! Parsing of `/proc/mounts` and `/etc/fstab`, so a [`PartitionID`] can be
+//! connected back to live mount state without shelling out to `findmnt`.
+
+use crate::PartitionID;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A single line of `/proc/mounts` or `/etc/fstab`.
+///
+/// The `fsname` field may be a device path, or a tagged spec such as
+/// `UUID=...` / `PARTUUID=...` / `LABEL=...` / `PARTLABEL=...`, which can be
+/// parsed with `fsname.parse::<PartitionID>()`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct MountEntry {
+    pub fsname: String,
+    pub dir: PathBuf,
+    pub fstype: String,
+    pub opts: String,
+    pub freq: u32,
+    pub passno: u32
+}
+
+impl MountEntry {
+    /// Parse this entry's `fsname` as a [`PartitionID`], if it is a tagged
+    /// spec or device path that `FromStr` understands.
+    pub fn partition_id(&self) -> Option<PartitionID> {
+        self.fsname.parse().ok()
+    }
+
+    /// Resolve this entry's `fsname` to the canonical device node it names,
+    /// following tagged specs through `/dev/disk/by-*` as needed.
+    ///
+    /// A bare device path is canonicalized directly rather than being
+    /// routed through `PartitionID::get_device_path`, which resolves a
+    /// `Path` variant by looking it up as a `by-path` alias, not as a
+    /// literal path.
+    pub fn device(&self) -> Option<PathBuf> {
+        if self.fsname.starts_with('/') {
+            return Path::new(&self.fsname).canonicalize().ok();
+        }
+
+        self.partition_id()?.get_device_path().ok()
+    }
+
+    fn parse_line(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let mut fields = line.split_whitespace();
+        let fsname = unescape_octal(fields.next()?);
+        let dir = PathBuf::from(unescape_octal(fields.next()?));
+        let fstype = fields.next()?.to_owned();
+        let opts = fields.next()?.to_owned();
+        let freq = fields.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+        let passno = fields.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+
+        Some(Self { fsname, dir, fstype, opts, freq, passno })
+    }
+}
+
+fn parse(contents: &str) -> Vec<MountEntry> {
+    contents.lines().filter_map(MountEntry::parse_line).collect()
+}
+
+/// Undo the octal whitespace escaping the kernel and `mount(8)` apply to
+/// `fsname`/`dir` fields (space, tab, newline, and backslash itself) so that
+/// paths containing those characters round-trip instead of keeping their
+/// literal `\040`-style escapes.
+fn unescape_octal(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let rest = chars.as_str();
+        let escaped = match rest.get(..3) {
+            Some("040") => Some(' '),
+            Some("011") => Some('\t'),
+            Some("012") => Some('\n'),
+            Some("134") => Some('\\'),
+            _ => None
+        };
+
+        match escaped {
+            Some(c) => {
+                result.push(c);
+                chars = rest[3..].chars();
+            }
+            None => result.push(c)
+        }
+    }
+
+    result
+}
+
+/// Parse `/proc/mounts`, returning every currently-mounted filesystem.
+pub fn mounts() -> io::Result<Vec<MountEntry>> {
+    fs::read_to_string("/proc/mounts").map(|contents| parse(&contents))
+}
+
+/// Parse `/etc/fstab`, returning every configured filesystem entry.
+pub fn fstab_entries() -> io::Result<Vec<MountEntry>> {
+    fs::read_to_string("/etc/fstab").map(|contents| parse(&contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_skips_blank_lines_and_comments() {
+        let entries = parse("\n# a comment\n/dev/sda1 / ext4 defaults 0 1\n");
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn parse_reads_all_fields() {
+        let entries = parse("UUID=abcd-1234 /boot/efi vfat umask=0077 0 2\n");
+        assert_eq!(
+            entries[0],
+            MountEntry {
+                fsname: "UUID=abcd-1234".into(),
+                dir: PathBuf::from("/boot/efi"),
+                fstype: "vfat".into(),
+                opts: "umask=0077".into(),
+                freq: 0,
+                passno: 2
+            }
+        );
+    }
+
+    #[test]
+    fn partition_id_parses_tagged_fsname() {
+        let entry = parse("UUID=abcd-1234 / ext4 defaults 0 1\n").remove(0);
+        assert_eq!(entry.partition_id(), Some(PartitionID::new_uuid("abcd-1234".into())));
+    }
+
+    #[test]
+    fn device_canonicalizes_a_bare_device_path() {
+        let path = std::env::temp_dir().join("partition-identity-test-fstab-device");
+        fs::write(&path, b"").unwrap();
+
+        let entry = MountEntry {
+            fsname: path.to_str().unwrap().to_owned(),
+            dir: PathBuf::from("/"),
+            fstype: "ext4".into(),
+            opts: "defaults".into(),
+            freq: 0,
+            passno: 1
+        };
+
+        let canonical = path.canonicalize().unwrap();
+        let device = entry.device();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(device, Some(canonical));
+    }
+
+    #[test]
+    fn unescape_octal_decodes_whitespace_and_backslash_escapes() {
+        assert_eq!(unescape_octal("My\\040Drive"), "My Drive");
+        assert_eq!(unescape_octal("a\\011b\\012c"), "a\tb\nc");
+        assert_eq!(unescape_octal("back\\134slash"), "back\\slash");
+        assert_eq!(unescape_octal("/dev/sda1"), "/dev/sda1");
+    }
+
+    #[test]
+    fn parse_unescapes_fsname_and_dir() {
+        let entries = parse("/dev/disk/by-label/My\\040Disk /mnt/My\\040Drive ext4 defaults 0 1\n");
+        assert_eq!(entries[0].fsname, "/dev/disk/by-label/My Disk");
+        assert_eq!(entries[0].dir, PathBuf::from("/mnt/My Drive"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Direct GPT header parsing, used as a fallback when `/dev/disk/by-partuuid`
+//! and `by-partlabel` have not been populated by udev yet (minimal containers,
+//! early boot, or freshly-created disks).
+
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const SECTOR_SIZE: u64 = 512;
+const SIGNATURE: &[u8; 8] = b"EFI PART";
+
+/// The PARTUUID and PARTLABEL of a single GPT partition entry.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct GptEntry {
+    pub partuuid: String,
+    pub partlabel: String
+}
+
+/// Read the GPT header and entry array of `disk`, and decode the entry for
+/// `partition_number` (1-indexed, matching kernel partition numbering).
+///
+/// Returns `Ok(None)` if the disk has no valid GPT, or if `partition_number`
+/// refers to an unused entry.
+pub fn read_entry(disk: &Path, partition_number: u32) -> io::Result<Option<GptEntry>> {
+    if partition_number == 0 {
+        return Ok(None);
+    }
+
+    let mut disk = File::open(disk)?;
+
+    let mut header = [0u8; 92];
+    disk.seek(SeekFrom::Start(SECTOR_SIZE))?;
+    disk.read_exact(&mut header)?;
+
+    if &header[0..8] != SIGNATURE {
+        return Ok(None);
+    }
+
+    let header_size = u32::from_le_bytes(header[12..16].try_into().unwrap()) as usize;
+    // The header must be at least big enough to cover every field we read
+    // from it (up to the entry size at offset 84..88) and no bigger than
+    // the buffer we read it into.
+    if header_size < 88 || header_size > header.len() {
+        return Ok(None);
+    }
+
+    let header_crc = u32::from_le_bytes(header[16..20].try_into().unwrap());
+    let mut crc_input = header[..header_size].to_vec();
+    crc_input[16..20].copy_from_slice(&[0, 0, 0, 0]);
+    if crc32(&crc_input) != header_crc {
+        return Ok(None);
+    }
+
+    let entries_lba = u64::from_le_bytes(header[72..80].try_into().unwrap());
+    let entry_count = u32::from_le_bytes(header[80..84].try_into().unwrap());
+    let entry_size = u32::from_le_bytes(header[84..88].try_into().unwrap()) as usize;
+
+    // Entries must be at least big enough to hold the type GUID, unique
+    // GUID, and partition name fields we read out of them below, and no
+    // larger than a real GPT entry ever is; a bogus multi-gigabyte claim
+    // here would otherwise turn into an equally bogus allocation below.
+    if entry_size < 128 || entry_size > 4096 || entry_count > 4096 {
+        return Ok(None);
+    }
+
+    if partition_number > entry_count {
+        return Ok(None);
+    }
+
+    let entry_offset = (partition_number as u64 - 1).checked_mul(entry_size as u64);
+    let offset = entry_offset.and_then(|entry_offset| entries_lba.checked_mul(SECTOR_SIZE)?.checked_add(entry_offset));
+    let offset = match offset {
+        Some(offset) => offset,
+        None => return Ok(None)
+    };
+
+    let mut entry = vec![0u8; entry_size];
+    disk.seek(SeekFrom::Start(offset))?;
+    disk.read_exact(&mut entry)?;
+
+    if entry[0..16].iter().all(|&b| b == 0) {
+        // An all-zero type GUID means the entry is unused.
+        return Ok(None);
+    }
+
+    let partuuid = format_guid(&entry[16..32]);
+    let partlabel = decode_utf16le(&entry[56..entry_size.min(56 + 72)]);
+
+    Ok(Some(GptEntry { partuuid, partlabel }))
+}
+
+/// Map a partition device node (e.g. `/dev/sda1`) back to its parent disk
+/// (e.g. `/dev/sda`) and its 1-indexed partition number, using the device's
+/// sysfs `partition` attribute.
+pub fn disk_and_partition_number(partition_path: &Path) -> io::Result<Option<(PathBuf, u32)>> {
+    let name = match partition_path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return Ok(None)
+    };
+
+    let sysfs_partition = PathBuf::from("/sys/class/block").join(name).join("partition");
+    let number = match fs::read_to_string(&sysfs_partition) {
+        Ok(contents) => match contents.trim().parse() {
+            Ok(number) => number,
+            Err(_) => return Ok(None)
+        },
+        Err(ref why) if why.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(why) => return Err(why)
+    };
+
+    let sysfs_block = PathBuf::from("/sys/class/block").join(name).canonicalize()?;
+    let disk_name = match sysfs_block.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()) {
+        Some(disk_name) => disk_name,
+        None => return Ok(None)
+    };
+
+    Ok(Some((PathBuf::from("/dev").join(disk_name), number)))
+}
+
+fn format_guid(bytes: &[u8]) -> String {
+    // GPT GUIDs are mixed-endian: the first three fields are little-endian,
+    // the last two are big-endian, matching Microsoft's GUID layout.
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+        u16::from_le_bytes(bytes[4..6].try_into().unwrap()),
+        u16::from_le_bytes(bytes[6..8].try_into().unwrap()),
+        bytes[8], bytes[9],
+        bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15]
+    )
+}
+
+fn decode_utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+
+    String::from_utf16_lossy(&units)
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_known_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn format_guid_is_mixed_endian() {
+        let bytes = [
+            0x78, 0x56, 0x34, 0x12, 0xbc, 0x9a, 0xf0, 0xde, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0
+        ];
+        assert_eq!(format_guid(&bytes), "12345678-9abc-def0-1234-56789abcdef0");
+    }
+
+    #[test]
+    fn decode_utf16le_stops_at_nul() {
+        let bytes = [b'h', 0, b'i', 0, 0, 0, b'X', 0];
+        assert_eq!(decode_utf16le(&bytes), "hi");
+    }
+
+    #[test]
+    fn read_entry_rejects_undersized_header_instead_of_panicking() {
+        let path = std::env::temp_dir().join("partition-identity-test-gpt-undersized");
+        let mut disk = vec![0u8; 512 + 92];
+        disk[512..520].copy_from_slice(SIGNATURE);
+        disk[512 + 12..512 + 16].copy_from_slice(&16u32.to_le_bytes());
+        fs::write(&path, &disk).unwrap();
+
+        let result = read_entry(&path, 1).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn read_entry_rejects_oversized_entry_size_instead_of_allocating() {
+        let path = std::env::temp_dir().join("partition-identity-test-gpt-oversized-entry");
+        let mut disk = vec![0u8; 512 + 92];
+        disk[512..520].copy_from_slice(SIGNATURE);
+        disk[512 + 12..512 + 16].copy_from_slice(&92u32.to_le_bytes());
+        disk[512 + 72..512 + 80].copy_from_slice(&u64::MAX.to_le_bytes());
+        disk[512 + 80..512 + 84].copy_from_slice(&1u32.to_le_bytes());
+        disk[512 + 84..512 + 88].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut crc_input = disk[512..512 + 92].to_vec();
+        crc_input[16..20].copy_from_slice(&[0, 0, 0, 0]);
+        let crc = crc32(&crc_input);
+        disk[512 + 16..512 + 20].copy_from_slice(&crc.to_le_bytes());
+
+        fs::write(&path, &disk).unwrap();
+        let result = read_entry(&path, 1).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn read_entry_decodes_a_valid_partition() {
+        let path = std::env::temp_dir().join("partition-identity-test-gpt-valid");
+        let mut disk = vec![0u8; 512 * 3];
+
+        let header_offset = 512;
+        disk[header_offset..header_offset + 8].copy_from_slice(SIGNATURE);
+        disk[header_offset + 12..header_offset + 16].copy_from_slice(&92u32.to_le_bytes());
+        disk[header_offset + 72..header_offset + 80].copy_from_slice(&2u64.to_le_bytes());
+        disk[header_offset + 80..header_offset + 84].copy_from_slice(&1u32.to_le_bytes());
+        disk[header_offset + 84..header_offset + 88].copy_from_slice(&128u32.to_le_bytes());
+
+        let mut crc_input = disk[header_offset..header_offset + 92].to_vec();
+        crc_input[16..20].copy_from_slice(&[0, 0, 0, 0]);
+        let crc = crc32(&crc_input);
+        disk[header_offset + 16..header_offset + 20].copy_from_slice(&crc.to_le_bytes());
+
+        let entry_offset = 512 * 2;
+        disk[entry_offset..entry_offset + 16].copy_from_slice(&[0xAA; 16]);
+        disk[entry_offset + 16..entry_offset + 32].copy_from_slice(&[
+            0x78, 0x56, 0x34, 0x12, 0xbc, 0x9a, 0xf0, 0xde, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0
+        ]);
+        let name: Vec<u8> = "root".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        disk[entry_offset + 56..entry_offset + 56 + name.len()].copy_from_slice(&name);
+
+        fs::write(&path, &disk).unwrap();
+        let result = read_entry(&path, 1).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let entry = result.unwrap();
+        assert_eq!(entry.partuuid, "12345678-9abc-def0-1234-56789abcdef0");
+        assert_eq!(entry.partlabel, "root");
+    }
+}
@@ -1,6 +1,19 @@
 //! Find the ID of a device by its path, or find a device path by its ID.
 
+mod device_map;
+mod disk;
+mod error;
+mod fstab;
+mod gpt;
+
+pub use device_map::{DeviceMap, Record};
+pub use disk::PartitionIdentities;
+pub use error::PartitionError;
+pub use fstab::{fstab_entries, mounts, MountEntry};
+pub use gpt::GptEntry;
+
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -49,34 +62,92 @@ impl PartitionID {
         Self::new(PartitionSource::Path, id)
     }
 
+    /// Construct a new `PartitionID` as a `DiskSeq` source.
+    pub fn new_diskseq(id: String) -> Self {
+        Self::new(PartitionSource::DiskSeq, id)
+    }
+
     /// Find the device path of this ID.
-    pub fn get_device_path(&self) -> Option<PathBuf> {
-        from_uuid(&self.id, Self::dir(self.variant))
+    ///
+    /// Falls back to reading the GPT directly for `PartUUID`/`PartLabel`
+    /// sources if no matching `by-*` symlink is found, which covers minimal
+    /// containers, early boot, and freshly-created disks before udev settles.
+    pub fn get_device_path(&self) -> Result<PathBuf, PartitionError> {
+        match Self::dir(self.variant) {
+            Ok(dir) => from_uuid(&self.id, dir)
+                .or_else(|| gpt_find_device(self.variant, &self.id))
+                .ok_or(PartitionError::NotFound),
+            Err(why) => gpt_find_device(self.variant, &self.id).ok_or(why)
+        }
     }
 
     /// Find the given source ID of the device at the given path.
-    pub fn get_source<P: AsRef<Path>>(variant: PartitionSource, path: P) -> Option<Self> {
-        Some(Self {
-            variant,
-            id: find_uuid(path.as_ref(), Self::dir(variant))?
-        })
+    ///
+    /// Falls back to reading the GPT directly for `PartUUID`/`PartLabel`
+    /// sources if no matching `by-*` symlink is found.
+    pub fn get_source<P: AsRef<Path>>(variant: PartitionSource, path: P) -> Result<Self, PartitionError> {
+        match Self::dir(variant).and_then(|dir| find_uuid(path.as_ref(), dir)) {
+            Ok(Some(id)) => Ok(Self { variant, id }),
+            Ok(None) => gpt_read_source(variant, path.as_ref()).ok_or(PartitionError::NotFound),
+            Err(why) => gpt_read_source(variant, path.as_ref()).ok_or(why)
+        }
+    }
+
+    /// Read the PARTUUID and PARTLABEL of partition `partition_number` on
+    /// `disk` directly from its GPT, bypassing `/dev/disk/by-*` entirely.
+    pub fn from_gpt<P: AsRef<Path>>(disk: P, partition_number: u32) -> Option<GptEntry> {
+        gpt::read_entry(disk.as_ref(), partition_number).ok()?
+    }
+
+    /// Find the PARTUUID of the device at `partition_path` by reading its
+    /// disk's GPT directly, mapping the partition back to its parent disk
+    /// and index via sysfs.
+    pub fn get_partuuid_from_gpt<P: AsRef<Path>>(partition_path: P) -> Option<Self> {
+        let (disk, number) = gpt::disk_and_partition_number(partition_path.as_ref()).ok()??;
+        let entry = gpt::read_entry(&disk, number).ok()??;
+        Some(Self::new_partuuid(entry.partuuid))
     }
 
     /// Find the UUID of the device at the given path.
-    pub fn get_uuid<P: AsRef<Path>>(path: P) -> Option<Self> {
+    pub fn get_uuid<P: AsRef<Path>>(path: P) -> Result<Self, PartitionError> {
         Self::get_source(PartitionSource::UUID, path)
     }
 
     /// Find the PARTUUID of the device at the given path.
-    pub fn get_partuuid<P: AsRef<Path>>(path: P) -> Option<Self> {
+    pub fn get_partuuid<P: AsRef<Path>>(path: P) -> Result<Self, PartitionError> {
         Self::get_source(PartitionSource::PartUUID, path)
     }
 
-    fn dir(variant: PartitionSource) -> fs::ReadDir {
-        let idpath = variant.disk_by_path();
-        idpath.read_dir().unwrap_or_else(|why| {
-            panic!(format!("unable to find {:?}: {}", idpath, why));
-        })
+    /// Find the disk sequence number of the device at the given path.
+    pub fn get_diskseq<P: AsRef<Path>>(path: P) -> Result<Self, PartitionError> {
+        Self::get_source(PartitionSource::DiskSeq, path)
+    }
+
+    /// Find the mount point of this partition, by matching its device
+    /// against every entry of `/proc/mounts`.
+    pub fn mount_point(&self) -> Option<PathBuf> {
+        let device = self.get_device_path().ok()?;
+        fstab::mounts()
+            .ok()?
+            .into_iter()
+            .find(|entry| entry.device().as_deref() == Some(device.as_path()))
+            .map(|entry| entry.dir)
+    }
+
+    /// Check whether this partition is currently mounted.
+    pub fn is_mounted(&self) -> bool {
+        self.mount_point().is_some()
+    }
+
+    /// Enumerate the partitions of the whole-disk device at `disk` (e.g.
+    /// `/dev/nvme0n1`), resolving every known identity of each one.
+    pub fn partitions_of<P: AsRef<Path>>(disk: P) -> io::Result<Vec<PartitionIdentities>> {
+        disk::partitions_of(disk.as_ref())
+    }
+
+    fn dir(variant: PartitionSource) -> Result<fs::ReadDir, PartitionError> {
+        let path = variant.disk_by_path();
+        path.read_dir().map_err(|source| PartitionError::DirUnavailable { path, source })
     }
 }
 
@@ -96,6 +167,8 @@ impl FromStr for PartitionID {
             Ok(PartitionID { variant: PartitionSource::PartUUID, id: input[9..].to_owned() })
         } else if input.starts_with("UUID=") {
             Ok(PartitionID { variant: PartitionSource::UUID, id: input[5..].to_owned() })
+        } else if input.starts_with("DISKSEQ=") {
+            Ok(PartitionID { variant: PartitionSource::DiskSeq, id: input[8..].to_owned() })
         } else {
             Err(format!("'{}' is not a valid PartitionID string", input))
         }
@@ -110,7 +183,8 @@ pub enum PartitionSource {
     PartLabel,
     PartUUID,
     Path,
-    UUID
+    UUID,
+    DiskSeq
 }
 
 impl From<PartitionSource> for &'static str {
@@ -121,7 +195,8 @@ impl From<PartitionSource> for &'static str {
             PartitionSource::PartLabel => "partlabel",
             PartitionSource::PartUUID => "partuuid",
             PartitionSource::Path => "path",
-            PartitionSource::UUID => "uuid"
+            PartitionSource::UUID => "uuid",
+            PartitionSource::DiskSeq => "diskseq"
         }
     }
 }
@@ -132,22 +207,81 @@ impl PartitionSource {
     }
 }
 
-fn find_uuid(path: &Path, uuid_dir: fs::ReadDir) -> Option<String> {
-    if let Ok(path) = path.canonicalize() {
-        for uuid_entry in uuid_dir.filter_map(|entry| entry.ok()) {
-            if let Ok(ref uuid_path) = uuid_entry.path().canonicalize() {
-                if uuid_path == &path {
-                    if let Some(uuid_entry) = uuid_entry.file_name().to_str() {
-                        return Some(uuid_entry.into());
-                    }
-                }
+/// Read the GPT entry of the disk behind `path`, mapping it back
+/// to its parent disk and index via sysfs first.
+fn gpt_read_source(variant: PartitionSource, path: &Path) -> Option<PartitionID> {
+    let id = match variant {
+        PartitionSource::PartUUID | PartitionSource::PartLabel => {
+            let (disk, number) = gpt::disk_and_partition_number(path).ok()??;
+            let entry = gpt::read_entry(&disk, number).ok()??;
+            match variant {
+                PartitionSource::PartUUID => entry.partuuid,
+                PartitionSource::PartLabel => entry.partlabel,
+                _ => unreachable!()
             }
         }
+        _ => return None
+    };
+
+    Some(PartitionID { variant, id })
+}
+
+/// Scan every block device under `/sys/class/block` for a GPT partition
+/// entry matching `id`, used when no `by-*` symlink resolves it.
+fn gpt_find_device(variant: PartitionSource, id: &str) -> Option<PathBuf> {
+    if variant != PartitionSource::PartUUID && variant != PartitionSource::PartLabel {
+        return None;
+    }
+
+    for entry in fs::read_dir("/sys/class/block").ok()?.filter_map(|entry| entry.ok()) {
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue
+        };
+
+        let partition_path = PathBuf::from("/dev").join(&name);
+        let (disk, number) = match gpt::disk_and_partition_number(&partition_path) {
+            Ok(Some(found)) => found,
+            _ => continue
+        };
+
+        let gpt_entry = match gpt::read_entry(&disk, number) {
+            Ok(Some(entry)) => entry,
+            _ => continue
+        };
+
+        let matches = match variant {
+            PartitionSource::PartUUID => gpt_entry.partuuid == id,
+            PartitionSource::PartLabel => gpt_entry.partlabel == id,
+            _ => false
+        };
+
+        if matches {
+            return partition_path.canonicalize().ok();
+        }
     }
 
     None
 }
 
+fn find_uuid(path: &Path, uuid_dir: fs::ReadDir) -> Result<Option<String>, PartitionError> {
+    let path = path
+        .canonicalize()
+        .map_err(|source| PartitionError::Canonicalize { path: path.to_owned(), source })?;
+
+    for uuid_entry in uuid_dir.filter_map(|entry| entry.ok()) {
+        if let Ok(ref uuid_path) = uuid_entry.path().canonicalize() {
+            if uuid_path == &path {
+                if let Some(uuid_entry) = uuid_entry.file_name().to_str() {
+                    return Ok(Some(uuid_entry.into()));
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
 fn from_uuid(uuid: &str, uuid_dir: fs::ReadDir) -> Option<PathBuf> {
     for uuid_entry in uuid_dir.filter_map(|entry| entry.ok()) {
         let uuid_entry = uuid_entry.path();
@@ -198,5 +332,10 @@ mod tests {
             "UUID=abcd".parse::<PartitionID>(),
             Ok(PartitionID::new_uuid("abcd".into()))
         );
+
+        assert_eq!(
+            "DISKSEQ=abcd".parse::<PartitionID>(),
+            Ok(PartitionID::new_diskseq("abcd".into()))
+        );
     }
 }